@@ -4,6 +4,13 @@ define_ctx_view!(
     name: S3CtxView,
     env {
         S3_REGION: String,
+        S3_ENDPOINT_URL: Option<String>,
+        S3_ACCESS_KEY_ID: Option<String>,
+        S3_SECRET_ACCESS_KEY: Option<String>,
+        S3_FORCE_PATH_STYLE: Option<bool>,
+        S3_MAX_RETRIES: Option<u32>,
+        S3_RETRY_MODE: Option<String>,
+        S3_RETRY_MAX_ELAPSED_SECS: Option<u64>,
     },
     secrets {},
     deps_overlay {
@@ -22,6 +29,13 @@ pub(crate) mod test_ctx {
         name: TestS3Ctx,
         env {
             S3_REGION: String,
+            S3_ENDPOINT_URL: Option<String>,
+            S3_ACCESS_KEY_ID: Option<String>,
+            S3_SECRET_ACCESS_KEY: Option<String>,
+            S3_FORCE_PATH_STYLE: Option<bool>,
+            S3_MAX_RETRIES: Option<u32>,
+            S3_RETRY_MODE: Option<String>,
+            S3_RETRY_MAX_ELAPSED_SECS: Option<u64>,
         },
         secrets_fetch_region: DUMMY,
         secrets_fetch_id: DUMMY,
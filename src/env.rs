@@ -1,8 +1,22 @@
 use fractic_env_config::{define_env_config, define_env_variable, EnvConfigEnum};
 
 define_env_variable!(S3_REGION);
+define_env_variable!(S3_ENDPOINT_URL);
+define_env_variable!(S3_ACCESS_KEY_ID);
+define_env_variable!(S3_SECRET_ACCESS_KEY);
+define_env_variable!(S3_FORCE_PATH_STYLE);
+define_env_variable!(S3_MAX_RETRIES);
+define_env_variable!(S3_RETRY_MODE);
+define_env_variable!(S3_RETRY_MAX_ELAPSED_SECS);
 
 define_env_config!(
     S3EnvConfig,
     S3Region => S3_REGION,
+    S3EndpointUrl => S3_ENDPOINT_URL,
+    S3AccessKeyId => S3_ACCESS_KEY_ID,
+    S3SecretAccessKey => S3_SECRET_ACCESS_KEY,
+    S3ForcePathStyle => S3_FORCE_PATH_STYLE,
+    S3MaxRetries => S3_MAX_RETRIES,
+    S3RetryMode => S3_RETRY_MODE,
+    S3RetryMaxElapsedSecs => S3_RETRY_MAX_ELAPSED_SECS,
 );
@@ -1,12 +1,17 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, collections::VecDeque, path::Path, sync::Arc};
 
 use aws_sdk_s3::{
     error::SdkError,
-    operation::head_object::HeadObjectError,
-    primitives::{ByteStream, SdkBody},
+    operation::{
+        complete_multipart_upload::CompleteMultipartUploadError, head_object::HeadObjectError,
+    },
+    primitives::{ByteStream, DateTime, Length, SdkBody},
+    types::{CompletedMultipartUpload, CompletedPart, Tag, Tagging},
+    waiters::object_exists::WaitUntilObjectExistsError,
 };
 use backend::S3Backend;
 use fractic_server_error::ServerError;
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
@@ -15,12 +20,39 @@ use crate::{
 };
 
 pub mod backend;
+mod retry;
 
 const WAIT_FOR_KEY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
 
+// S3 requires every part except the last to be at least 5 MiB.
+const MIN_MULTIPART_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+// Default cap on how long the manual retry helper (see `util::retry`) will
+// keep retrying an operation the SDK doesn't already retry for us, when
+// S3_RETRY_MAX_ELAPSED_SECS isn't set. Kept comfortably above
+// `WAIT_FOR_KEY_TIMEOUT` so `wait_until_key_exists` (its motivating case)
+// gets at least one retry by default, rather than exhausting the whole
+// budget on a single waiter attempt.
+const DEFAULT_RETRY_MAX_ELAPSED: std::time::Duration = std::time::Duration::from_secs(150);
+
 pub struct S3Util {
     pub backend: Arc<dyn S3Backend>,
     pub bucket: String,
+    retry_max_elapsed: std::time::Duration,
+    // Normalized (no leading/trailing slash), transparently prepended to
+    // every key. See `S3Util::new_with_prefix`.
+    prefix_in_bucket: Option<String>,
+}
+
+/// Metadata for a single entry yielded by [`S3Util::list_stream`]. When the
+/// entry is a common prefix (a "folder", returned when a `delimiter` is
+/// used), only `key` is populated.
+#[derive(Debug, Clone)]
+pub struct ObjectMeta {
+    pub key: String,
+    pub size: Option<i64>,
+    pub last_modified: Option<DateTime>,
+    pub e_tag: Option<String>,
 }
 
 pub struct S3KeyGenerator {}
@@ -42,12 +74,44 @@ impl S3KeyGenerator {
 
 impl S3Util {
     pub async fn new(ctx: &dyn S3CtxView, bucket: impl Into<String>) -> Result<Self, ServerError> {
+        Self::new_with_prefix(ctx, bucket, None::<String>).await
+    }
+
+    /// Like [`S3Util::new`], but every key passed to `put`/`get`/`head`/
+    /// `copy`/`delete`/`list`/presign operations (and `S3KeyGenerator`
+    /// output) is transparently scoped under `prefix_in_bucket`, and
+    /// stripped back off of keys returned by `list`/`list_stream`. This
+    /// lets independent tenants share one bucket without seeing each
+    /// other's objects.
+    pub async fn new_with_prefix(
+        ctx: &dyn S3CtxView,
+        bucket: impl Into<String>,
+        prefix_in_bucket: impl Into<Option<String>>,
+    ) -> Result<Self, ServerError> {
         Ok(Self {
             backend: ctx.s_3_backend().await?,
             bucket: bucket.into(),
+            retry_max_elapsed: ctx
+                .s3_retry_max_elapsed_secs()
+                .map(|secs| std::time::Duration::from_secs(*secs))
+                .unwrap_or(DEFAULT_RETRY_MAX_ELAPSED),
+            prefix_in_bucket: prefix_in_bucket.into().and_then(normalize_prefix),
         })
     }
 
+    // Joins `prefix_in_bucket` (if set) onto `key`, normalizing slashes so a
+    // trailing `/` on the prefix or a leading `/` on the key never produces
+    // a doubled separator.
+    fn scoped_key(&self, key: String) -> String {
+        scope_key(&self.prefix_in_bucket, key)
+    }
+
+    // Inverse of `scoped_key`, for keys coming back from S3 (e.g. via
+    // `list`). Returns `key` unchanged if it doesn't start with the prefix.
+    fn unscoped_key(&self, key: String) -> String {
+        unscope_key(&self.prefix_in_bucket, &key)
+    }
+
     pub async fn put_serializable<T: Serialize>(
         &self,
         key: String,
@@ -57,7 +121,7 @@ impl S3Util {
             .map_err(|e| S3InvalidOperation::with_debug("failed to serialize object", &e))?;
         let body = ByteStream::new(SdkBody::from(serialized));
         self.backend
-            .put_object(self.bucket.clone(), key, body, None)
+            .put_object(self.bucket.clone(), self.scoped_key(key), body, None)
             .await
             .map_err(|e| S3CalloutError::with_debug("failed to put serializable", &e))?;
         Ok(())
@@ -69,7 +133,7 @@ impl S3Util {
     ) -> Result<T, ServerError> {
         let output = self
             .backend
-            .get_object(self.bucket.clone(), key)
+            .get_object(self.bucket.clone(), self.scoped_key(key))
             .await
             .map_err(|_| S3NotFound::new())?;
         let bytes = output
@@ -83,6 +147,33 @@ impl S3Util {
         Ok(deserialized)
     }
 
+    /// Returns the object body as a `ByteStream`, without buffering it into
+    /// memory. Use `.into_async_read()` on the result to get an `AsyncRead`.
+    pub async fn get_stream(&self, key: String) -> Result<ByteStream, ServerError> {
+        let output = self
+            .backend
+            .get_object(self.bucket.clone(), self.scoped_key(key))
+            .await
+            .map_err(|_| S3NotFound::new())?;
+        Ok(output.body)
+    }
+
+    /// Returns a byte range `[range.start, range.end)` of the object as a
+    /// `ByteStream`, without buffering the whole object into memory.
+    pub async fn get_range(
+        &self,
+        key: String,
+        range: std::ops::Range<u64>,
+    ) -> Result<ByteStream, ServerError> {
+        let range_header = format!("bytes={}-{}", range.start, range.end.saturating_sub(1));
+        let output = self
+            .backend
+            .get_object_range(self.bucket.clone(), self.scoped_key(key), range_header)
+            .await
+            .map_err(|_| S3NotFound::new())?;
+        Ok(output.body)
+    }
+
     pub async fn upload_file(
         &self,
         key: String,
@@ -93,17 +184,120 @@ impl S3Util {
             .await
             .map_err(|e| S3InvalidOperation::with_debug("failed to open file", &e))?;
         self.backend
-            .put_object(self.bucket.clone(), key, body, metadata)
+            .put_object(self.bucket.clone(), self.scoped_key(key), body, metadata)
             .await
             .map_err(|e| S3CalloutError::with_debug("failed to upload file", &e))?;
         Ok(())
     }
 
+    /// Uploads a file in multiple parts, uploading up to `concurrency` parts
+    /// at a time. Every part but the last is `part_size` bytes, raised to
+    /// 5 MiB (the S3 minimum for all but the last part) if smaller; the last
+    /// part holds the remainder. Falls back to a single `upload_file` call
+    /// when the file is no larger than that part size.
+    ///
+    /// If any part fails, the upload is aborted so no incomplete multipart
+    /// upload is left behind.
+    pub async fn upload_file_multipart(
+        &self,
+        key: String,
+        filename: &str,
+        part_size: u64,
+        concurrency: usize,
+    ) -> Result<(), ServerError> {
+        let part_size = part_size.max(MIN_MULTIPART_PART_SIZE);
+        let file_size = tokio::fs::metadata(filename)
+            .await
+            .map_err(|e| S3InvalidOperation::with_debug("failed to stat file", &e))?
+            .len();
+
+        if file_size <= part_size {
+            return self.upload_file(key, filename, None).await;
+        }
+        let key = self.scoped_key(key);
+
+        let upload_id = self
+            .backend
+            .create_multipart_upload(self.bucket.clone(), key.clone())
+            .await
+            .map_err(|e| S3CalloutError::with_debug("failed to create multipart upload", &e))?
+            .upload_id()
+            .ok_or_else(|| {
+                S3CalloutError::with_debug(
+                    "multipart upload response missing upload id",
+                    &"CreateMultipartUpload",
+                )
+            })?
+            .to_string();
+
+        let parts = multipart_part_plan(file_size, part_size);
+
+        let mut completed_parts = Vec::with_capacity(parts.len());
+        let mut upload_failure = None;
+        for batch in parts.chunks(concurrency.max(1)) {
+            let mut tasks = tokio::task::JoinSet::new();
+            for &(part_number, offset, length) in batch {
+                tasks.spawn(upload_multipart_part(
+                    self.backend.clone(),
+                    self.bucket.clone(),
+                    key.clone(),
+                    upload_id.clone(),
+                    filename.to_string(),
+                    part_number,
+                    offset,
+                    length,
+                ));
+            }
+            while let Some(result) = tasks.join_next().await {
+                match result
+                    .map_err(|e| S3CalloutError::with_debug("upload part task panicked", &e))
+                {
+                    Ok(Ok(completed_part)) => completed_parts.push(completed_part),
+                    Ok(Err(e)) | Err(e) => {
+                        upload_failure.get_or_insert(e);
+                    }
+                }
+            }
+            if upload_failure.is_some() {
+                break;
+            }
+        }
+
+        if let Some(e) = upload_failure {
+            let _ = self
+                .backend
+                .abort_multipart_upload(self.bucket.clone(), key, upload_id)
+                .await;
+            return Err(e);
+        }
+
+        completed_parts.sort_by_key(|part| part.part_number());
+        let is_retryable = |e: &SdkError<CompleteMultipartUploadError>| {
+            matches!(e, SdkError::TimeoutError(_) | SdkError::DispatchFailure(_))
+                || retry::looks_transient(e)
+        };
+        retry::retry_with_backoff(self.retry_max_elapsed, is_retryable, || {
+            self.backend.complete_multipart_upload(
+                self.bucket.clone(),
+                key.clone(),
+                upload_id.clone(),
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed_parts.clone()))
+                    .build(),
+            )
+        })
+        .await
+        .map_err(|e| S3CalloutError::with_debug("failed to complete multipart upload", &e))?;
+        Ok(())
+    }
+
     pub async fn move_object(
         &self,
         source_key: String,
         target_key: String,
     ) -> Result<(), ServerError> {
+        let source_key = self.scoped_key(source_key);
+        let target_key = self.scoped_key(target_key);
         self.backend
             .copy_object(self.bucket.clone(), source_key.clone(), target_key)
             .await
@@ -117,14 +311,51 @@ impl S3Util {
 
     pub async fn delete_object(&self, key: String) -> Result<(), ServerError> {
         self.backend
-            .delete_object(self.bucket.clone(), key)
+            .delete_object(self.bucket.clone(), self.scoped_key(key))
             .await
             .map_err(|e| S3CalloutError::with_debug("failed to delete object", &e))?;
         Ok(())
     }
 
+    /// Deletes every key under `prefix` using batched `DeleteObjects` calls
+    /// instead of one round-trip per key. Returns the `(key, error)` pairs
+    /// for any keys S3 failed to delete, rather than aborting on the first
+    /// failure.
+    pub async fn delete_prefix(
+        &self,
+        prefix: String,
+    ) -> Result<Vec<(String, ServerError)>, ServerError> {
+        let keys = self.list(prefix).await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let scoped_keys = keys.into_iter().map(|key| self.scoped_key(key)).collect();
+        let errors = self
+            .backend
+            .delete_objects(self.bucket.clone(), scoped_keys)
+            .await
+            .map_err(|e| S3CalloutError::with_debug("failed to delete objects", &e))?;
+        Ok(errors
+            .into_iter()
+            .map(|e| {
+                let key = self.unscoped_key(e.key().unwrap_or_default().to_string());
+                (
+                    key,
+                    S3CalloutError::with_debug(
+                        "failed to delete object",
+                        &e.message().unwrap_or_default(),
+                    ),
+                )
+            })
+            .collect())
+    }
+
     pub async fn key_exists(&self, key: String) -> Result<bool, ServerError> {
-        match self.backend.head_object(self.bucket.clone(), key).await {
+        match self
+            .backend
+            .head_object(self.bucket.clone(), self.scoped_key(key))
+            .await
+        {
             Ok(_) => Ok(true),
             Err(sdk_error) => match sdk_error {
                 SdkError::ServiceError(e) => match e.err() {
@@ -147,7 +378,11 @@ impl S3Util {
         &self,
         key: String,
     ) -> Result<Option<HashMap<String, String>>, ServerError> {
-        match self.backend.head_object(self.bucket.clone(), key).await {
+        match self
+            .backend
+            .head_object(self.bucket.clone(), self.scoped_key(key))
+            .await
+        {
             Ok(output) => Ok(Some(output.metadata.unwrap_or_default())),
             Err(sdk_error) => match sdk_error {
                 SdkError::ServiceError(e) => match e.err() {
@@ -165,11 +400,68 @@ impl S3Util {
         }
     }
 
-    pub async fn wait_until_key_exists(&self, key: String) -> Result<(), ServerError> {
+    pub async fn get_tags(&self, key: String) -> Result<HashMap<String, String>, ServerError> {
+        let output = self
+            .backend
+            .get_object_tagging(self.bucket.clone(), self.scoped_key(key))
+            .await
+            .map_err(|e| S3CalloutError::with_debug("failed to get object tags", &e))?;
+        Ok(output
+            .tag_set()
+            .iter()
+            .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+            .collect())
+    }
+
+    pub async fn set_tags(
+        &self,
+        key: String,
+        tags: HashMap<String, String>,
+    ) -> Result<(), ServerError> {
+        let tag_set = tags
+            .into_iter()
+            .map(|(tag_key, tag_value)| {
+                Tag::builder()
+                    .key(tag_key)
+                    .value(tag_value)
+                    .build()
+                    .map_err(|e| S3ItemParsingError::with_debug("failed to build tag", &e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let tagging = Tagging::builder()
+            .set_tag_set(Some(tag_set))
+            .build()
+            .map_err(|e| S3ItemParsingError::with_debug("failed to build tagging", &e))?;
+        self.backend
+            .put_object_tagging(self.bucket.clone(), self.scoped_key(key), tagging)
+            .await
+            .map_err(|e| S3CalloutError::with_debug("failed to set object tags", &e))?;
+        Ok(())
+    }
+
+    pub async fn clear_tags(&self, key: String) -> Result<(), ServerError> {
         self.backend
-            .wait_until_object_exists(self.bucket.clone(), key, WAIT_FOR_KEY_TIMEOUT)
+            .delete_object_tagging(self.bucket.clone(), self.scoped_key(key))
             .await
-            .map_err(|e| S3CalloutError::with_debug("failed to wait for key existance", &e))?;
+            .map_err(|e| S3CalloutError::with_debug("failed to clear object tags", &e))?;
+        Ok(())
+    }
+
+    pub async fn wait_until_key_exists(&self, key: String) -> Result<(), ServerError> {
+        let key = self.scoped_key(key);
+        // Unlike `complete_multipart_upload`'s `SdkError`, a waiter failure
+        // (most commonly just exceeding `WAIT_FOR_KEY_TIMEOUT` before the
+        // object showed up) doesn't carry an S3 error code for
+        // `retry::looks_transient` to key off of, so every waiter outcome is
+        // retried up to `retry_max_elapsed` — that's the whole point of
+        // "wait until it exists".
+        let is_retryable = |_: &WaitUntilObjectExistsError| true;
+        retry::retry_with_backoff(self.retry_max_elapsed, is_retryable, || {
+            self.backend
+                .wait_until_object_exists(self.bucket.clone(), key.clone(), WAIT_FOR_KEY_TIMEOUT)
+        })
+        .await
+        .map_err(|e| S3CalloutError::with_debug("failed to wait for key existance", &e))?;
         Ok(())
     }
 
@@ -180,7 +472,7 @@ impl S3Util {
     ) -> Result<String, ServerError> {
         let presigned_request = self
             .backend
-            .generate_presigned_url(self.bucket.clone(), key, expires_in)
+            .generate_presigned_url(self.bucket.clone(), self.scoped_key(key), expires_in)
             .await
             .map_err(|e| S3CalloutError::with_debug("failed to generate presigned URL", &e))?;
         Ok(presigned_request.uri().into())
@@ -189,7 +481,7 @@ impl S3Util {
     pub async fn get_size(&self, key: String) -> Result<i64, ServerError> {
         let output = self
             .backend
-            .head_object(self.bucket.clone(), key)
+            .head_object(self.bucket.clone(), self.scoped_key(key))
             .await
             .map_err(|e| S3CalloutError::with_debug("failed to get object size", &e))?;
         Ok(output.content_length.unwrap_or_default())
@@ -197,9 +489,258 @@ impl S3Util {
 
     /// List all keys in the bucket that start with `key_prefix`.
     pub async fn list(&self, key_prefix: String) -> Result<Vec<String>, ServerError> {
-        self.backend
-            .list_keys(self.bucket.clone(), key_prefix)
+        let keys = self
+            .backend
+            .list_keys(self.bucket.clone(), self.scoped_key(key_prefix))
             .await
-            .map_err(|e| S3CalloutError::with_debug("failed to list keys", &e))
+            .map_err(|e| S3CalloutError::with_debug("failed to list keys", &e))?;
+        Ok(keys
+            .into_iter()
+            .map(|key| self.unscoped_key(key))
+            .collect())
+    }
+
+    /// Lazily lists entries under `key_prefix`, fetching one `ListObjectsV2`
+    /// page at a time as the stream is polled, rather than buffering every
+    /// key up front.
+    ///
+    /// If `delimiter` is set, keys containing it (after `key_prefix`) are
+    /// collapsed into a single common-prefix entry instead of being listed
+    /// individually, mirroring directory-style listing (e.g. `delimiter:
+    /// Some("/".to_string())` lists only the immediate "folder" contents).
+    pub fn list_stream(
+        &self,
+        key_prefix: String,
+        delimiter: Option<String>,
+    ) -> impl Stream<Item = Result<ObjectMeta, ServerError>> {
+        let state = ListStreamState {
+            backend: self.backend.clone(),
+            bucket: self.bucket.clone(),
+            prefix: self.scoped_key(key_prefix),
+            prefix_in_bucket: self.prefix_in_bucket.clone(),
+            delimiter,
+            continuation_token: None,
+            pending: VecDeque::new(),
+            finished: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((Ok(item), state));
+                }
+                if state.finished {
+                    return None;
+                }
+
+                let page = match state
+                    .backend
+                    .list_objects_page(
+                        state.bucket.clone(),
+                        state.prefix.clone(),
+                        state.delimiter.clone(),
+                        state.continuation_token.clone(),
+                    )
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        state.finished = true;
+                        return Some((
+                            Err(S3CalloutError::with_debug("failed to list objects", &e)),
+                            state,
+                        ));
+                    }
+                };
+
+                state
+                    .pending
+                    .extend(page.contents().iter().map(|obj| ObjectMeta {
+                        key: unscope_key(&state.prefix_in_bucket, obj.key().unwrap_or_default()),
+                        size: obj.size(),
+                        last_modified: obj.last_modified().cloned(),
+                        e_tag: obj.e_tag().map(|s| s.to_string()),
+                    }));
+                state
+                    .pending
+                    .extend(page.common_prefixes().iter().filter_map(|p| {
+                        p.prefix().map(|prefix| ObjectMeta {
+                            key: unscope_key(&state.prefix_in_bucket, prefix),
+                            size: None,
+                            last_modified: None,
+                            e_tag: None,
+                        })
+                    }));
+
+                state.continuation_token = if page.is_truncated().unwrap_or(false) {
+                    page.next_continuation_token().map(|s| s.to_string())
+                } else {
+                    None
+                };
+                if state.continuation_token.is_none() {
+                    state.finished = true;
+                }
+            }
+        })
+    }
+}
+
+// Trims slashes off `prefix`, returning `None` if nothing is left (so an
+// empty or all-slashes prefix behaves like no prefix at all).
+fn normalize_prefix(prefix: String) -> Option<String> {
+    let trimmed = prefix.trim_matches('/');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+// Cursor state driving `list_stream`'s page-by-page paginator.
+struct ListStreamState {
+    backend: Arc<dyn S3Backend>,
+    bucket: String,
+    prefix: String,
+    prefix_in_bucket: Option<String>,
+    delimiter: Option<String>,
+    continuation_token: Option<String>,
+    pending: VecDeque<ObjectMeta>,
+    finished: bool,
+}
+
+// Free-standing equivalent of `S3Util::scoped_key`.
+fn scope_key(prefix_in_bucket: &Option<String>, key: String) -> String {
+    match prefix_in_bucket {
+        Some(prefix) => format!("{prefix}/{}", key.trim_start_matches('/')),
+        None => key,
+    }
+}
+
+// Free-standing equivalent of `S3Util::unscoped_key`, for use from
+// `list_stream`'s `'static` paginator closure, which has no `&self`.
+fn unscope_key(prefix_in_bucket: &Option<String>, key: &str) -> String {
+    match prefix_in_bucket {
+        Some(prefix) => key
+            .strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(key)
+            .to_string(),
+        None => key.to_string(),
+    }
+}
+
+// Splits a `file_size`-byte file into `(part_number, offset, length)` triples
+// of at most `part_size` bytes each, 1-indexed as S3 part numbers require.
+// Assumes `part_size` has already been clamped to `MIN_MULTIPART_PART_SIZE`.
+fn multipart_part_plan(file_size: u64, part_size: u64) -> Vec<(i32, u64, u64)> {
+    (0..file_size)
+        .step_by(part_size as usize)
+        .enumerate()
+        .map(|(i, offset)| {
+            let length = part_size.min(file_size - offset);
+            ((i + 1) as i32, offset, length)
+        })
+        .collect()
+}
+
+// Reads and uploads a single multipart part from a byte range of `filename`.
+// Standalone (rather than a method) so it can be spawned as an owned,
+// 'static task for concurrent part uploads.
+async fn upload_multipart_part(
+    backend: Arc<dyn S3Backend>,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    filename: String,
+    part_number: i32,
+    offset: u64,
+    length: u64,
+) -> Result<CompletedPart, ServerError> {
+    let body = ByteStream::read_from()
+        .path(filename)
+        .offset(offset)
+        .length(Length::Exact(length))
+        .build()
+        .await
+        .map_err(|e| S3InvalidOperation::with_debug("failed to read file part", &e))?;
+    let output = backend
+        .upload_part(bucket, key, upload_id, part_number, body)
+        .await
+        .map_err(|e| S3CalloutError::with_debug("failed to upload part", &e))?;
+    Ok(CompletedPart::builder()
+        .set_e_tag(output.e_tag().map(|s| s.to_string()))
+        .part_number(part_number)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multipart_part_plan_splits_into_equal_parts_plus_remainder() {
+        assert_eq!(
+            multipart_part_plan(25, 10),
+            vec![(1, 0, 10), (2, 10, 10), (3, 20, 5)],
+        );
+    }
+
+    #[test]
+    fn multipart_part_plan_exact_multiple_has_no_short_final_part() {
+        assert_eq!(multipart_part_plan(20, 10), vec![(1, 0, 10), (2, 10, 10)]);
+    }
+
+    #[test]
+    fn multipart_part_plan_empty_file_has_no_parts() {
+        assert_eq!(multipart_part_plan(0, 10), Vec::new());
+    }
+
+    #[test]
+    fn scope_key_joins_with_single_slash() {
+        assert_eq!(
+            scope_key(&Some("tenant-a".to_string()), "foo.txt".to_string()),
+            "tenant-a/foo.txt",
+        );
+    }
+
+    #[test]
+    fn scope_key_normalizes_leading_slash_on_key() {
+        assert_eq!(
+            scope_key(&Some("tenant-a".to_string()), "/foo.txt".to_string()),
+            "tenant-a/foo.txt",
+        );
+    }
+
+    #[test]
+    fn scope_key_is_noop_without_prefix() {
+        assert_eq!(scope_key(&None, "foo.txt".to_string()), "foo.txt");
+    }
+
+    #[test]
+    fn unscope_key_strips_prefix_added_by_scope_key() {
+        let prefix = Some("tenant-a".to_string());
+        let scoped = scope_key(&prefix, "dir/foo.txt".to_string());
+        assert_eq!(unscope_key(&prefix, &scoped), "dir/foo.txt");
+    }
+
+    #[test]
+    fn unscope_key_leaves_unprefixed_keys_unchanged() {
+        assert_eq!(
+            unscope_key(&Some("tenant-a".to_string()), "other-tenant/foo.txt"),
+            "other-tenant/foo.txt",
+        );
+    }
+
+    #[test]
+    fn normalize_prefix_trims_leading_and_trailing_slashes() {
+        assert_eq!(
+            normalize_prefix("/tenant-a/".to_string()),
+            Some("tenant-a".to_string()),
+        );
+    }
+
+    #[test]
+    fn normalize_prefix_empty_or_all_slashes_is_none() {
+        assert_eq!(normalize_prefix("".to_string()), None);
+        assert_eq!(normalize_prefix("///".to_string()), None);
     }
 }
@@ -1,26 +1,39 @@
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use aws_config::{BehaviorVersion, Region};
+use aws_config::{retry::RetryConfig, BehaviorVersion, Region};
 use aws_sdk_s3::{
     client::Waiters,
+    config::Credentials,
     error::SdkError,
     operation::{
+        abort_multipart_upload::{AbortMultipartUploadError, AbortMultipartUploadOutput},
+        complete_multipart_upload::{CompleteMultipartUploadError, CompleteMultipartUploadOutput},
         copy_object::{CopyObjectError, CopyObjectOutput},
+        create_multipart_upload::{CreateMultipartUploadError, CreateMultipartUploadOutput},
         delete_object::{DeleteObjectError, DeleteObjectOutput},
+        delete_object_tagging::{DeleteObjectTaggingError, DeleteObjectTaggingOutput},
+        delete_objects::DeleteObjectsError,
         get_object::{GetObjectError, GetObjectOutput},
+        get_object_tagging::{GetObjectTaggingError, GetObjectTaggingOutput},
         head_object::{HeadObjectError, HeadObjectOutput},
-        list_objects_v2::ListObjectsV2Error,
+        list_objects_v2::{ListObjectsV2Error, ListObjectsV2Output},
         put_object::{PutObjectError, PutObjectOutput},
+        put_object_tagging::{PutObjectTaggingError, PutObjectTaggingOutput},
+        upload_part::{UploadPartError, UploadPartOutput},
     },
     presigning::{PresignedRequest, PresigningConfig},
     primitives::ByteStream,
+    types::{CompletedMultipartUpload, Delete, Error as S3ObjectError, ObjectIdentifier, Tagging},
     waiters::object_exists::{ObjectExistsFinalPoll, WaitUntilObjectExistsError},
 };
 use fractic_context::register_ctx_singleton;
 
 use crate::S3CtxView;
 
+// S3's DeleteObjects API accepts at most this many keys per request.
+const MAX_DELETE_OBJECTS_BATCH_SIZE: usize = 1000;
+
 // Underlying backend, which performs the actual AWS operations. Kept generic so
 // that it can be swapped with a mock backend for testing.
 //
@@ -43,6 +56,14 @@ pub trait S3Backend: Send + Sync {
         key: String,
     ) -> Result<GetObjectOutput, SdkError<GetObjectError>>;
 
+    // `range` is the raw HTTP Range header value, e.g. "bytes=0-1023".
+    async fn get_object_range(
+        &self,
+        bucket: String,
+        key: String,
+        range: String,
+    ) -> Result<GetObjectOutput, SdkError<GetObjectError>>;
+
     async fn head_object(
         &self,
         bucket: String,
@@ -69,18 +90,89 @@ pub trait S3Backend: Send + Sync {
         expires_in: Duration,
     ) -> Result<PresignedRequest, SdkError<GetObjectError>>;
 
+    // Deletes all `keys` from `bucket`, auto-chunking into batches of at
+    // most `MAX_DELETE_OBJECTS_BATCH_SIZE`. Per-key failures that S3 reports
+    // in a response don't fail the call: they are collected and returned.
+    // A request that fails as a whole returns its error right away, and the
+    // per-key errors gathered from earlier batches are dropped.
+    async fn delete_objects(
+        &self,
+        bucket: String,
+        keys: Vec<String>,
+    ) -> Result<Vec<S3ObjectError>, SdkError<DeleteObjectsError>>;
+
     async fn list_keys(
         &self,
         bucket: String,
         prefix: String,
     ) -> Result<Vec<String>, SdkError<ListObjectsV2Error>>;
 
+    // Fetches a single `ListObjectsV2` page, to be driven by a paginator.
+    // `delimiter`, when set, groups keys under it into `common_prefixes`
+    // rather than listing them individually.
+    async fn list_objects_page(
+        &self,
+        bucket: String,
+        prefix: String,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsV2Output, SdkError<ListObjectsV2Error>>;
+
     async fn wait_until_object_exists(
         &self,
         bucket: String,
         key: String,
         timeout: Duration,
     ) -> Result<ObjectExistsFinalPoll, WaitUntilObjectExistsError>;
+
+    async fn get_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<GetObjectTaggingOutput, SdkError<GetObjectTaggingError>>;
+
+    async fn put_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+        tagging: Tagging,
+    ) -> Result<PutObjectTaggingOutput, SdkError<PutObjectTaggingError>>;
+
+    async fn delete_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<DeleteObjectTaggingOutput, SdkError<DeleteObjectTaggingError>>;
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<CreateMultipartUploadOutput, SdkError<CreateMultipartUploadError>>;
+
+    async fn upload_part(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+        part_number: i32,
+        body: ByteStream,
+    ) -> Result<UploadPartOutput, SdkError<UploadPartError>>;
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+        parts: CompletedMultipartUpload,
+    ) -> Result<CompleteMultipartUploadOutput, SdkError<CompleteMultipartUploadError>>;
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+    ) -> Result<AbortMultipartUploadOutput, SdkError<AbortMultipartUploadError>>;
 }
 
 // Real implementation,
@@ -113,6 +205,20 @@ impl S3Backend for aws_sdk_s3::Client {
         self.get_object().bucket(bucket).key(key).send().await
     }
 
+    async fn get_object_range(
+        &self,
+        bucket: String,
+        key: String,
+        range: String,
+    ) -> Result<GetObjectOutput, SdkError<GetObjectError>> {
+        self.get_object()
+            .bucket(bucket)
+            .key(key)
+            .range(range)
+            .send()
+            .await
+    }
+
     async fn head_object(
         &self,
         bucket: String,
@@ -169,6 +275,136 @@ impl S3Backend for aws_sdk_s3::Client {
             .await
     }
 
+    async fn get_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<GetObjectTaggingOutput, SdkError<GetObjectTaggingError>> {
+        self.get_object_tagging()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    async fn put_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+        tagging: Tagging,
+    ) -> Result<PutObjectTaggingOutput, SdkError<PutObjectTaggingError>> {
+        self.put_object_tagging()
+            .bucket(bucket)
+            .key(key)
+            .tagging(tagging)
+            .send()
+            .await
+    }
+
+    async fn delete_object_tagging(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<DeleteObjectTaggingOutput, SdkError<DeleteObjectTaggingError>> {
+        self.delete_object_tagging()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+    ) -> Result<CreateMultipartUploadOutput, SdkError<CreateMultipartUploadError>> {
+        self.create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+        part_number: i32,
+        body: ByteStream,
+    ) -> Result<UploadPartOutput, SdkError<UploadPartError>> {
+        self.upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(body)
+            .send()
+            .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+        parts: CompletedMultipartUpload,
+    ) -> Result<CompleteMultipartUploadOutput, SdkError<CompleteMultipartUploadError>> {
+        self.complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(parts)
+            .send()
+            .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: String,
+        key: String,
+        upload_id: String,
+    ) -> Result<AbortMultipartUploadOutput, SdkError<AbortMultipartUploadError>> {
+        self.abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+    }
+
+    async fn delete_objects(
+        &self,
+        bucket: String,
+        keys: Vec<String>,
+    ) -> Result<Vec<S3ObjectError>, SdkError<DeleteObjectsError>> {
+        let mut errors = Vec::new();
+        for chunk in keys.chunks(MAX_DELETE_OBJECTS_BATCH_SIZE) {
+            let objects = chunk
+                .iter()
+                .map(|key| {
+                    ObjectIdentifier::builder()
+                        .key(key.clone())
+                        .build()
+                        .expect("key is always set")
+                })
+                .collect::<Vec<_>>();
+            let output = self
+                .delete_objects()
+                .bucket(bucket.clone())
+                .delete(
+                    Delete::builder()
+                        .set_objects(Some(objects))
+                        .build()
+                        .expect("objects is always set"),
+                )
+                .send()
+                .await?;
+            errors.extend(output.errors().to_vec());
+        }
+        Ok(errors)
+    }
+
     async fn list_keys(
         &self,
         bucket: String,
@@ -210,20 +446,75 @@ impl S3Backend for aws_sdk_s3::Client {
 
         Ok(keys)
     }
+
+    async fn list_objects_page(
+        &self,
+        bucket: String,
+        prefix: String,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsV2Output, SdkError<ListObjectsV2Error>> {
+        self.list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_delimiter(delimiter)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+    }
 }
 
 // Register dependency, default to real AWS backend.
 // --------------------------------------------------
+//
+// When S3_ENDPOINT_URL / S3_ACCESS_KEY_ID / S3_SECRET_ACCESS_KEY are not set,
+// this falls back to the default AWS endpoint + credential chain (the region
+// always comes from S3_REGION), so it keeps
+// working unmodified against real AWS. When they are set, it targets an
+// S3-compatible store (e.g. MinIO, Garage, Ceph) instead, which typically
+// also requires path-style addressing (S3_FORCE_PATH_STYLE).
 
 register_ctx_singleton!(
     dyn S3CtxView,
     dyn S3Backend,
     |ctx: Arc<dyn S3CtxView>| async move {
         let region = Region::new(ctx.s3_region().clone());
-        let shared_config = aws_config::defaults(BehaviorVersion::v2025_01_17())
-            .region(region)
-            .load()
-            .await;
-        Ok(aws_sdk_s3::Client::new(&shared_config))
+        let mut config_loader =
+            aws_config::defaults(BehaviorVersion::v2025_01_17()).region(region);
+
+        if let Some(endpoint_url) = ctx.s3_endpoint_url() {
+            config_loader = config_loader.endpoint_url(endpoint_url.clone());
+        }
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (ctx.s3_access_key_id(), ctx.s3_secret_access_key())
+        {
+            config_loader = config_loader.credentials_provider(Credentials::new(
+                access_key_id.clone(),
+                secret_access_key.clone(),
+                None,
+                None,
+                "fractic-aws-s3-static",
+            ));
+        }
+
+        let mut retry_config = match ctx.s3_retry_mode().map(|mode| mode.as_str()) {
+            Some("adaptive") => RetryConfig::adaptive(),
+            _ => RetryConfig::standard(),
+        };
+        if let Some(max_retries) = ctx.s3_max_retries() {
+            // `with_max_attempts` counts the initial attempt (and panics on
+            // 0), so add one to turn the retry count into an attempt count;
+            // S3_MAX_RETRIES=0 then means a single attempt with no retries.
+            retry_config = retry_config.with_max_attempts((*max_retries).saturating_add(1));
+        }
+        config_loader = config_loader.retry_config(retry_config);
+
+        let shared_config = config_loader.load().await;
+        let mut client_config = aws_sdk_s3::config::Builder::from(&shared_config);
+        if ctx.s3_force_path_style().copied().unwrap_or(false) {
+            client_config = client_config.force_path_style(true);
+        }
+
+        Ok(aws_sdk_s3::Client::from_conf(client_config.build()))
     }
 );
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use aws_sdk_s3::error::ProvideErrorMetadata;
+
+// Retries `operation` with exponential backoff (plus jitter) while
+// `is_retryable` returns true for the error, up to `max_elapsed`.
+//
+// This is for the handful of operations not already covered by the SDK's
+// own retry policy (see S3_MAX_RETRIES / S3_RETRY_MODE in `register_ctx_singleton!`),
+// such as the `wait_until_object_exists` waiter and multipart completion.
+pub(crate) async fn retry_with_backoff<T, E, Fut>(
+    max_elapsed: Duration,
+    is_retryable: impl Fn(&E) -> bool,
+    mut operation: impl FnMut() -> Fut,
+) -> Result<T, E>
+where
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    let start = Instant::now();
+    let mut delay = Duration::from_millis(200);
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !is_retryable(&e) || start.elapsed() >= max_elapsed {
+                    return Err(e);
+                }
+                tokio::time::sleep(jittered(delay)).await;
+                delay = (delay * 2).min(Duration::from_secs(30));
+            }
+        }
+    }
+}
+
+// Adds +/-25% jitter without pulling in a dedicated RNG dependency.
+fn jittered(delay: Duration) -> Duration {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let factor = 0.75 + (nanos as f64 / u32::MAX as f64) * 0.5;
+    delay.mul_f64(factor)
+}
+
+// S3 error codes worth an application-level retry, on top of whatever the
+// SDK's own retry policy (S3_MAX_RETRIES / S3_RETRY_MODE) already covers.
+const RETRYABLE_ERROR_CODES: &[&str] = &[
+    "SlowDown",
+    "RequestTimeout",
+    "RequestTimeTooSkewed",
+    "ServiceUnavailable",
+    "InternalError",
+    "ThrottlingException",
+];
+
+// True if `error`'s S3 error code (via `ProvideErrorMetadata`) is one of
+// `RETRYABLE_ERROR_CODES`. This also works directly on `SdkError<E>`, which
+// forwards to the inner service error's code. Not applicable to waiter
+// errors (e.g. `WaitUntilObjectExistsError`): those are a generic
+// smithy wrapper with no `ProvideErrorMetadata` impl of their own, and their
+// dominant failure mode (exceeding the waiter's timeout) carries no error
+// code to begin with — see `S3Util::wait_until_key_exists`.
+pub(crate) fn looks_transient<E: ProvideErrorMetadata>(error: &E) -> bool {
+    matches!(error.code(), Some(code) if RETRYABLE_ERROR_CODES.contains(&code))
+}